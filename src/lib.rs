@@ -4,25 +4,115 @@ use deno_core::plugin_api::Op;
 use deno_core::plugin_api::ZeroCopyBuf;
 
 use serde::Deserialize;
+use serde::Serialize;
 
 use core::{analyzer, parser};
+use swc_core::ast_parser::{
+    AstParser, Diagnostic, MediaType, ParseOptions, SwcDiagnosticBuffer, TranspileOptions,
+};
+
+mod lossy_string;
+use lossy_string::LossyString;
 
 #[no_mangle]
 pub fn deno_plugin_init(interface: &mut dyn Interface) {
     interface.register_op("parse", op_parse);
     interface.register_op("parse_ts", op_parse_ts);
     interface.register_op("extract_dependencies", ops_extract_dependencies);
+    interface.register_op("transpile", op_transpile);
+    interface.register_op("extract_comments", op_extract_comments);
 }
 
 #[derive(Deserialize)]
 struct ParseArguments {
-    src: String,
+    src: LossyString,
+    #[serde(default = "default_file_name")]
+    file_name: String,
+    #[serde(default = "default_media_type")]
+    media_type: String,
+    #[serde(default)]
+    jsx: bool,
+    #[serde(default)]
+    decorators: bool,
+    #[serde(default = "default_target")]
+    target: String,
+}
+
+fn default_file_name() -> String {
+    "<anonymous>".to_string()
+}
+
+fn default_media_type() -> String {
+    "ts".to_string()
+}
+
+fn default_target() -> String {
+    "es2019".to_string()
+}
+
+fn parse_options(media_type: &str, jsx: bool, decorators: bool, target: &str) -> ParseOptions {
+    ParseOptions {
+        media_type: MediaType::from_str(media_type),
+        jsx,
+        decorators,
+        target: swc_core::ast_parser::target_from_str(target),
+    }
+}
+
+/// A diagnostic resolved to a concrete location in the supplied file, for
+/// JS callers that want to point at the exact offending source position.
+#[derive(Serialize)]
+struct DiagnosticRecord {
+    message: String,
+    file: String,
+    line: usize,
+    col: usize,
+}
+
+fn diagnostic_records(file_name: &str, diagnostics: Vec<Diagnostic>) -> Vec<DiagnosticRecord> {
+    diagnostics
+        .into_iter()
+        .map(|d| DiagnosticRecord {
+            message: d.message,
+            file: file_name.to_string(),
+            line: d.line,
+            col: d.col,
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct TranspileArguments {
+    src: LossyString,
+    #[serde(default = "default_file_name")]
+    file_name: String,
+    #[serde(default)]
+    minify: bool,
+    #[serde(default, rename = "sourceMaps")]
+    source_maps: bool,
+    #[serde(default = "default_media_type")]
+    media_type: String,
+    #[serde(default)]
+    jsx: bool,
+    #[serde(default)]
+    decorators: bool,
+    #[serde(default = "default_target")]
+    target: String,
+}
+
+#[derive(Serialize)]
+struct TranspileResult {
+    code: String,
+    map: Option<String>,
+    diagnostics: Vec<DiagnosticRecord>,
 }
 
 #[derive(Deserialize)]
 struct AnalyzerArguments {
-    src: String,
+    src: LossyString,
     dynamic: bool,
+    #[serde(default = "default_file_name")]
+    file_name: String,
 }
 
 #[allow(clippy::needless_return)]
@@ -35,9 +125,9 @@ fn ops_extract_dependencies(_interface: &mut dyn Interface, zero_copy: &mut [Zer
             let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
             Op::Sync(result_box)
         }
-        Err(_) => {
-            //TODO: return actual error message instead of "parse_error"
-            let result = serde_json::to_string("parse_error").expect("failed to serialize Deps");
+        Err(diagnostics) => {
+            let records = diagnostic_records(&params.file_name, diagnostics);
+            let result = serde_json::to_string(&records).expect("failed to serialize diagnostics");
             let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
             Op::Sync(result_box)
         }
@@ -47,22 +137,110 @@ fn ops_extract_dependencies(_interface: &mut dyn Interface, zero_copy: &mut [Zer
 fn op_parse(_interface: &mut dyn Interface, zero_copy: &mut [ZeroCopyBuf]) -> Op {
     let data = &zero_copy[0][..];
     let params: ParseArguments = serde_json::from_slice(&data).unwrap();
-    let program = parser::parse_js(params.src);
+    let program = parser::parse_js(params.src.into_inner());
     let result = serde_json::to_string(&program).expect("failed to serialize Program");
     let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
     Op::Sync(result_box)
 }
 
+#[derive(Serialize)]
+struct ParseResult {
+    ast: serde_json::Value,
+    diagnostics: Vec<DiagnosticRecord>,
+}
+
 fn op_parse_ts(_interface: &mut dyn Interface, zero_copy: &mut [ZeroCopyBuf]) -> Op {
     let data = &zero_copy[0][..];
     let params: ParseArguments = serde_json::from_slice(&data).unwrap();
-    let program = parser::parse_ts(params.src);
-    let result = match program {
-        Ok(ast) => serde_json::to_string(&ast).expect("failed to serialize Program"),
-        Err(message) => {
-            serde_json::to_string(&message.to_string()).expect("failed to serialize Program")
+    let options = parse_options(
+        &params.media_type,
+        params.jsx,
+        params.decorators,
+        &params.target,
+    );
+    let (ast, diagnostics) = parser::parse_ts(params.src.into_inner(), &options);
+    let parse_result = ParseResult {
+        ast,
+        diagnostics: diagnostic_records(&params.file_name, diagnostics),
+    };
+    let result = serde_json::to_string(&parse_result).expect("failed to serialize ParseResult");
+    let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
+    Op::Sync(result_box)
+}
+
+fn op_transpile(_interface: &mut dyn Interface, zero_copy: &mut [ZeroCopyBuf]) -> Op {
+    let data = &zero_copy[0][..];
+    let params: TranspileArguments = serde_json::from_slice(&data).unwrap();
+    let ast_parser = AstParser::new();
+    let transpile_options = TranspileOptions {
+        minify: params.minify,
+        source_maps: params.source_maps,
+    };
+    let options = parse_options(
+        &params.media_type,
+        params.jsx,
+        params.decorators,
+        &params.target,
+    );
+
+    let transpiled = ast_parser.parse_module(
+        &params.file_name,
+        &params.src,
+        &options,
+        |module, diagnostics| {
+            ast_parser
+                .transpile(module, &transpile_options)
+                .map(|transpiled| (transpiled, diagnostics))
+        },
+    );
+
+    let transpile_result = match transpiled {
+        Ok((module, diagnostics)) => {
+            let diagnostics = SwcDiagnosticBuffer::from_error_buffer(diagnostics, |span| {
+                ast_parser.source_map.lookup_char_pos(span.lo())
+            });
+            TranspileResult {
+                code: module.code,
+                map: module.map,
+                diagnostics: diagnostic_records(&params.file_name, diagnostics),
+            }
         }
+        Err(err) => TranspileResult {
+            code: String::new(),
+            map: None,
+            diagnostics: vec![DiagnosticRecord {
+                message: err.to_string(),
+                file: params.file_name.clone(),
+                line: 0,
+                col: 0,
+            }],
+        },
     };
+    let result =
+        serde_json::to_string(&transpile_result).expect("failed to serialize TranspileResult");
+    let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
+    Op::Sync(result_box)
+}
+
+fn op_extract_comments(_interface: &mut dyn Interface, zero_copy: &mut [ZeroCopyBuf]) -> Op {
+    let data = &zero_copy[0][..];
+    let params: ParseArguments = serde_json::from_slice(&data).unwrap();
+    let options = parse_options(
+        &params.media_type,
+        params.jsx,
+        params.decorators,
+        &params.target,
+    );
+    let ast_parser = AstParser::new();
+    ast_parser.parse_module(
+        &params.file_name,
+        &params.src,
+        &options,
+        |_module, _diagnostics| {},
+    );
+    let comments = ast_parser.collected_comments();
+
+    let result = serde_json::to_string(&comments).expect("failed to serialize CollectedComments");
     let result_box: Buf = serde_json::to_vec(&result).unwrap().into_boxed_slice();
     Op::Sync(result_box)
 }
@@ -0,0 +1,173 @@
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde_json::value::RawValue;
+
+/// A `String` that tolerates JSON `\uXXXX` escapes encoding lone surrogate
+/// halves, substituting U+FFFD instead of letting `serde_json` error out.
+///
+/// Editor buffers and the REPL can hand the plugin JSON whose string
+/// literals contain unpaired surrogates (e.g. a half-typed emoji). Decoding
+/// those with the regular `String` deserializer aborts the whole Deno
+/// process, so op arguments carrying arbitrary source text use this type
+/// instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LossyString(pub String);
+
+impl LossyString {
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl std::ops::Deref for LossyString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for LossyString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // `RawValue` captures the literal verbatim, escapes and all, instead
+        // of decoding it up front the way `String`/`&str` do - that decoding
+        // step is exactly what errors out on a lone surrogate escape. It accepts
+        // any JSON value, though, so a non-string value has to be rejected
+        // by hand to keep the same "expected a string" contract `String`
+        // gives callers.
+        let raw = <&RawValue>::deserialize(deserializer)?;
+        let text = raw.get();
+        if !text.starts_with('"') {
+            return Err(D::Error::invalid_type(
+                serde::de::Unexpected::Other(text),
+                &"a string",
+            ));
+        }
+        Ok(LossyString(decode_lossy(text)))
+    }
+}
+
+/// Decodes a raw JSON string literal (including its surrounding quotes),
+/// resolving `\uXXXX` escapes by hand so that unpaired surrogate halves
+/// become U+FFFD instead of a decode error.
+fn decode_lossy(raw: &str) -> String {
+    let inner = raw
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(raw);
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    let mut pending_high_surrogate: Option<u16> = None;
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            flush_pending_surrogate(&mut pending_high_surrogate, &mut out);
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('u') => {
+                let code = read_hex4(&mut chars);
+                match code {
+                    0xD800..=0xDBFF => {
+                        flush_pending_surrogate(&mut pending_high_surrogate, &mut out);
+                        pending_high_surrogate = Some(code);
+                    }
+                    0xDC00..=0xDFFF => match pending_high_surrogate.take() {
+                        Some(high) => out.push(decode_surrogate_pair(high, code)),
+                        None => out.push('\u{FFFD}'),
+                    },
+                    _ => {
+                        flush_pending_surrogate(&mut pending_high_surrogate, &mut out);
+                        out.push(char::from_u32(code as u32).unwrap_or('\u{FFFD}'));
+                    }
+                }
+            }
+            Some(escaped) => {
+                flush_pending_surrogate(&mut pending_high_surrogate, &mut out);
+                out.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    other => other,
+                });
+            }
+            None => flush_pending_surrogate(&mut pending_high_surrogate, &mut out),
+        }
+    }
+
+    flush_pending_surrogate(&mut pending_high_surrogate, &mut out);
+    out
+}
+
+fn flush_pending_surrogate(pending: &mut Option<u16>, out: &mut String) {
+    if pending.take().is_some() {
+        out.push('\u{FFFD}');
+    }
+}
+
+/// Defensive fallback for fewer than 4 hex digits; unreachable through
+/// [`LossyString::deserialize`] since serde_json's tokenizer already
+/// guarantees 4 digits follow `\u` before a string token is produced.
+fn read_hex4(chars: &mut impl Iterator<Item = char>) -> u16 {
+    let hex: String = chars.take(4).collect();
+    u16::from_str_radix(&hex, 16).unwrap_or(0xFFFD)
+}
+
+fn decode_surrogate_pair(high: u16, low: u16) -> char {
+    let combined = 0x10000 + (((high - 0xD800) as u32) << 10) + (low - 0xDC00) as u32;
+    char::from_u32(combined).unwrap_or('\u{FFFD}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(json_literal: &str) -> String {
+        serde_json::from_str::<LossyString>(json_literal)
+            .unwrap()
+            .into_inner()
+    }
+
+    #[test]
+    fn decodes_plain_text() {
+        assert_eq!(decode(r#""hello""#), "hello");
+    }
+
+    #[test]
+    fn decodes_valid_surrogate_pair_escape() {
+        let escaped = "\"\\ud83d\\ude00\"";
+        assert_eq!(decode(escaped), "\u{1F600}");
+    }
+
+    #[test]
+    fn replaces_lone_high_surrogate() {
+        assert_eq!(decode(r#""a\ud83db""#), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn replaces_lone_low_surrogate() {
+        assert_eq!(decode(r#""a\ude00b""#), "a\u{FFFD}b");
+    }
+
+    // A truncated `\u` escape (fewer than 4 hex digits) never reaches
+    // `decode_lossy`: serde_json's own tokenizer requires 4 hex digits
+    // before it will hand `RawValue` a string token at all, so that input
+    // is rejected upstream with a JSON syntax error, same as `String`.
+    #[test]
+    fn truncated_unicode_escape_is_a_json_error() {
+        assert!(serde_json::from_str::<LossyString>(r#""a\u""#).is_err());
+    }
+
+    #[test]
+    fn rejects_non_string_json_value() {
+        let result = serde_json::from_str::<LossyString>("42");
+        assert!(result.is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use std::error::Error;
 use std::fmt;
 use std::sync::Arc;
@@ -5,17 +6,93 @@ use std::sync::RwLock;
 use swc_common::errors::Emitter;
 use swc_common::{
     self,
-    comments::Comments,
-    errors::{Diagnostic, DiagnosticBuilder, Handler, HandlerFlags},
-    FileName, Globals, SourceMap,
+    comments::{Comment, CommentKind, Comments},
+    errors::{Diagnostic as SwcDiagnostic, DiagnosticBuilder, Handler, HandlerFlags},
+    FileName, Globals, Loc, SourceMap, Span,
 };
+use swc_ecma_codegen::{text_writer::JsWriter, Config as CodegenConfig, Emitter as AstEmitter};
 use swc_ecma_parser::{
-    lexer::Lexer, JscTarget, Parser, Session, SourceFileInput, Syntax, TsConfig,
+    lexer::Lexer, EsConfig, JscTarget, Parser, Session, SourceFileInput, Syntax, TsConfig,
 };
+use swc_ecma_transforms::{fixer::fixer, resolver::resolver, typescript::strip};
+use swc_ecma_visit::FoldWith;
+
+/// The grammar a source file should be parsed as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaType {
+    JavaScript,
+    Jsx,
+    TypeScript,
+    Tsx,
+}
+
+impl Default for MediaType {
+    fn default() -> Self {
+        MediaType::TypeScript
+    }
+}
+
+impl MediaType {
+    pub fn from_str(media_type: &str) -> Self {
+        match media_type {
+            "js" | "javascript" => MediaType::JavaScript,
+            "jsx" => MediaType::Jsx,
+            "tsx" => MediaType::Tsx,
+            _ => MediaType::TypeScript,
+        }
+    }
+}
+
+/// Options controlling how [`AstParser::parse_module`] interprets a source
+/// file: its grammar, whether JSX/decorators are enabled, and the ECMA
+/// target handed to the lexer.
+#[derive(Clone, Copy, Debug)]
+pub struct ParseOptions {
+    pub media_type: MediaType,
+    pub jsx: bool,
+    pub decorators: bool,
+    pub target: JscTarget,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            media_type: MediaType::default(),
+            jsx: false,
+            decorators: false,
+            target: JscTarget::Es2019,
+        }
+    }
+}
+
+/// Maps a target version string (e.g. `"es2020"`) to the matching
+/// `JscTarget`, falling back to `Es2019` for anything unrecognized.
+pub fn target_from_str(target: &str) -> JscTarget {
+    match target {
+        "es3" => JscTarget::Es3,
+        "es5" => JscTarget::Es5,
+        "es2015" => JscTarget::Es2015,
+        "es2016" => JscTarget::Es2016,
+        "es2017" => JscTarget::Es2017,
+        "es2018" => JscTarget::Es2018,
+        "es2019" => JscTarget::Es2019,
+        "es2020" => JscTarget::Es2020,
+        _ => JscTarget::Es2019,
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct SwcDiagnosticBuffer {
-    pub diagnostics: Vec<Diagnostic>,
+    pub diagnostics: Vec<SwcDiagnostic>,
+}
+
+/// A single diagnostic resolved to a concrete source location, suitable for
+/// handing back to JS callers instead of an opaque error string.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
 }
 
 impl Error for SwcDiagnosticBuffer {}
@@ -57,6 +134,74 @@ impl From<SwcErrorBuffer> for SwcDiagnosticBuffer {
     }
 }
 
+impl SwcDiagnosticBuffer {
+    /// Resolves every diagnostic's primary span to a line/column via
+    /// `get_loc` (typically `SourceMap::lookup_char_pos`), so callers can
+    /// point at the exact offending location instead of a flat message.
+    pub fn from_error_buffer(
+        buf: SwcDiagnosticBuffer,
+        get_loc: impl Fn(Span) -> Loc,
+    ) -> Vec<Diagnostic> {
+        buf.diagnostics
+            .iter()
+            .map(|d| {
+                let loc = get_loc(d.span.primary_span().unwrap_or_default());
+                Diagnostic {
+                    message: d.message(),
+                    line: loc.line,
+                    col: loc.col.0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Options controlling how [`AstParser::transpile`] emits its output.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TranspileOptions {
+    pub minify: bool,
+    pub source_maps: bool,
+}
+
+/// The result of transpiling a module down to JavaScript.
+#[derive(Clone, Debug)]
+pub struct TranspiledModule {
+    pub code: String,
+    pub map: Option<String>,
+}
+
+/// A single comment attached to a byte position, as recorded by the
+/// `Comments` collector passed to the lexer.
+#[derive(Clone, Debug, Serialize)]
+pub struct CommentEntry {
+    pub kind: &'static str,
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl CommentEntry {
+    fn from_comment(comment: &Comment) -> Self {
+        CommentEntry {
+            kind: match comment.kind {
+                CommentKind::Line => "line",
+                CommentKind::Block => "block",
+            },
+            text: comment.text.to_string(),
+            start: comment.span.lo().0,
+            end: comment.span.hi().0,
+        }
+    }
+}
+
+/// All comments collected while parsing, keyed by the byte offset of the
+/// node/token they attach to.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CollectedComments {
+    pub leading: std::collections::HashMap<u32, Vec<CommentEntry>>,
+    pub trailing: std::collections::HashMap<u32, Vec<CommentEntry>>,
+}
+
 /// Low-level utility structure with common AST parsing functions.
 ///
 /// Allows to build more complicated parser by providing a callback
@@ -91,9 +236,19 @@ impl AstParser {
         }
     }
 
-    pub fn parse_module<F, R>(&self, file_name: &str, source_code: &str, callback: F) -> R
+    /// Parses `source_code` and always hands the callback a best-effort
+    /// `Module` alongside whatever diagnostics were collected, mirroring
+    /// SWC's `take_errors()` flow so a single recoverable syntax slip
+    /// doesn't discard the whole tree.
+    pub fn parse_module<F, R>(
+        &self,
+        file_name: &str,
+        source_code: &str,
+        options: &ParseOptions,
+        callback: F,
+    ) -> R
     where
-        F: FnOnce(Result<swc_ecma_ast::Module, SwcDiagnosticBuffer>) -> R,
+        F: FnOnce(swc_ecma_ast::Module, SwcDiagnosticBuffer) -> R,
     {
         swc_common::GLOBALS.set(&self.globals, || {
             let swc_source_file = self.source_map.new_source_file(
@@ -101,35 +256,138 @@ impl AstParser {
                 source_code.to_string(),
             );
 
-            let buffered_err = self.buffered_error.clone();
             let session = Session {
                 handler: &self.handler,
             };
 
-            let mut ts_config = TsConfig::default();
-            ts_config.dynamic_import = true;
-            let syntax = Syntax::Typescript(ts_config);
+            let syntax = match options.media_type {
+                MediaType::JavaScript => Syntax::Es(EsConfig {
+                    jsx: options.jsx,
+                    ..Default::default()
+                }),
+                MediaType::Jsx => Syntax::Es(EsConfig {
+                    jsx: true,
+                    ..Default::default()
+                }),
+                MediaType::TypeScript => Syntax::Typescript(TsConfig {
+                    tsx: false,
+                    decorators: options.decorators,
+                    dynamic_import: true,
+                    ..Default::default()
+                }),
+                MediaType::Tsx => Syntax::Typescript(TsConfig {
+                    tsx: true,
+                    decorators: options.decorators,
+                    dynamic_import: true,
+                    ..Default::default()
+                }),
+            };
 
             let lexer = Lexer::new(
                 session,
                 syntax,
-                JscTarget::Es2019,
+                options.target,
                 SourceFileInput::from(&*swc_source_file),
                 Some(&self.comments),
             );
 
             let mut parser = Parser::new_from(session, lexer);
 
-            let parse_result = parser
-                .parse_module()
-                .map_err(move |mut err: DiagnosticBuilder| {
-                    err.cancel();
-                    SwcDiagnosticBuffer::from(buffered_err)
-                });
+            let module = parser.parse_module().unwrap_or_else(|mut err| {
+                err.emit();
+                swc_ecma_ast::Module {
+                    span: swc_common::DUMMY_SP,
+                    body: vec![],
+                    shebang: None,
+                }
+            });
+
+            for mut recoverable in parser.take_errors() {
+                recoverable.emit();
+            }
+
+            let diagnostics = SwcDiagnosticBuffer::from(self.buffered_error.clone());
+
+            callback(module, diagnostics)
+        })
+    }
+
+    /// Strips TypeScript types from `module` and emits plain JavaScript,
+    /// optionally alongside a source map.
+    pub fn transpile(
+        &self,
+        module: swc_ecma_ast::Module,
+        options: &TranspileOptions,
+    ) -> Result<TranspiledModule, std::io::Error> {
+        swc_common::GLOBALS.set(&self.globals, || {
+            let module = module
+                .fold_with(&mut resolver())
+                .fold_with(&mut strip())
+                .fold_with(&mut fixer(Some(&self.comments)));
+
+            let mut src_map_buf = vec![];
+            let mut buf = vec![];
+            {
+                let writer = JsWriter::new(
+                    self.source_map.clone(),
+                    "\n",
+                    &mut buf,
+                    options.source_maps.then(|| &mut src_map_buf),
+                );
+                let mut emitter = AstEmitter {
+                    cfg: CodegenConfig {
+                        minify: options.minify,
+                    },
+                    comments: Some(&self.comments),
+                    cm: self.source_map.clone(),
+                    wr: Box::new(writer),
+                };
+                emitter.emit_module(&module)?;
+            }
+
+            let code = String::from_utf8(buf).expect("swc codegen did not produce valid utf-8");
+            let map = if options.source_maps {
+                let mut map_buf = vec![];
+                self.source_map
+                    .build_source_map(&src_map_buf)
+                    .to_writer(&mut map_buf)
+                    .expect("failed to write source map");
+                Some(String::from_utf8(map_buf).expect("source map is not valid utf-8"))
+            } else {
+                None
+            };
 
-            callback(parse_result)
+            Ok(TranspiledModule { code, map })
         })
     }
+
+    /// Reads back the leading/trailing comments collected while parsing,
+    /// keyed by the byte position of the node/token they attach to. Does
+    /// not remove anything from the underlying `Comments` maps, so it may
+    /// be called more than once against the same `AstParser`.
+    ///
+    /// Reuses the `comments` field and source map already built up during
+    /// `parse_module`, so it enables doc generation and JSDoc-based type
+    /// hints without re-lexing the source.
+    pub fn collected_comments(&self) -> CollectedComments {
+        let mut leading = std::collections::HashMap::new();
+        for (pos, comments) in self.comments.leading_map().borrow().iter() {
+            leading.insert(
+                pos.0,
+                comments.iter().map(CommentEntry::from_comment).collect(),
+            );
+        }
+
+        let mut trailing = std::collections::HashMap::new();
+        for (pos, comments) in self.comments.trailing_map().borrow().iter() {
+            trailing.insert(
+                pos.0,
+                comments.iter().map(CommentEntry::from_comment).collect(),
+            );
+        }
+
+        CollectedComments { leading, trailing }
+    }
 }
 
 impl Default for AstParser {
@@ -137,3 +395,26 @@ impl Default for AstParser {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_module_surfaces_malformed_source_as_diagnostics() {
+        let ast_parser = AstParser::new();
+        let options = ParseOptions::default();
+
+        let diagnostics = ast_parser.parse_module(
+            "malformed.ts",
+            "class {",
+            &options,
+            |_module, diagnostics| diagnostics,
+        );
+
+        assert!(
+            !diagnostics.diagnostics.is_empty(),
+            "malformed source should produce at least one diagnostic instead of being cancelled"
+        );
+    }
+}